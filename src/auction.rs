@@ -1,15 +1,36 @@
-use crate::auction_config::{Config, SiteConfig};
+use crate::auction_config::{AuctionType, Config};
 
 use serde::{Deserialize, Serialize, Serializer};
+use sha2::{Digest, Sha256};
 
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+/// The two phases of a commit-reveal sealed-bid auction (see [`AuctionPhase`]).
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionPhase {
+    /// Bidders have only published `commitment` hashes; no bid values are known yet.
+    Commit,
+    /// Bidders have revealed `bid` and `nonce`, which are checked against `commitment`.
+    Reveal,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Bid {
     pub bidder: String,
     pub unit: String,
-    #[serde(serialize_with = "serialize_float")]
-    pub bid: f64,
+    #[serde(default, serialize_with = "serialize_optional_float")]
+    pub bid: Option<f64>,
+    /// `hex(sha256(bid_value_bytes || nonce))`, published during the commit phase.
+    #[serde(default)]
+    pub commitment: Option<String>,
+    /// Published alongside `bid` during the reveal phase to let the commitment be checked.
+    #[serde(default)]
+    pub nonce: Option<String>,
+    /// Unix timestamp the bid was recorded at, checked against the auction's `start_ts`/`end_ts`.
+    #[serde(default)]
+    pub ts: Option<i64>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -17,61 +38,362 @@ pub struct Auction {
     pub site: String,
     pub units: Vec<String>,
     pub bids: Vec<Bid>,
+    /// `None` means bids carry an already-cleared `bid` value, as in a plain first-/second-price auction.
+    #[serde(default)]
+    pub phase: Option<AuctionPhase>,
+    /// Unix timestamp the auction opens at. `None` means there's no lower bound.
+    #[serde(default)]
+    pub start_ts: Option<i64>,
+    /// Unix timestamp the auction closes at. `None` means there's no upper bound.
+    #[serde(default)]
+    pub end_ts: Option<i64>,
+}
+
+/// An auction's lifecycle state relative to a point in time (see [`auction_status`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuctionStatus {
+    /// `now` is before the auction's `start_ts`.
+    NotStarted,
+    /// `now` is within the auction's `[start_ts, end_ts]` window.
+    Open,
+    /// `now` is after the auction's `end_ts`.
+    Ended,
+}
+
+/// Classifies `auction`'s lifecycle state at `now_ts`, based on its `start_ts`/`end_ts`.
+/// An auction with no bounds on one side is never `NotStarted`/`Ended` on that side.
+pub fn auction_status(auction: &Auction, now_ts: i64) -> AuctionStatus {
+    if let Some(start_ts) = auction.start_ts {
+        if now_ts < start_ts {
+            return AuctionStatus::NotStarted;
+        }
+    }
+
+    if let Some(end_ts) = auction.end_ts {
+        if now_ts > end_ts {
+            return AuctionStatus::Ended;
+        }
+    }
+
+    AuctionStatus::Open
+}
+
+/// Rejects every bid in `auction` with [`BidRejectionReason::AuctionNotOpen`], for callers
+/// that have already determined (via [`auction_status`]) that the auction isn't open, so a
+/// `--explain`-style report still says *why* it produced no winners instead of going silent.
+pub fn reject_closed_auction(auction: &Auction) -> Vec<BidRejection<'_>> {
+    auction
+        .bids
+        .iter()
+        .map(|bid| BidRejection {
+            bidder: Cow::Borrowed(&bid.bidder),
+            unit: Cow::Borrowed(&bid.unit),
+            reason: BidRejectionReason::AuctionNotOpen,
+            adjusted_bid_value: resolve_bid_value(bid, auction.phase).unwrap_or(0.0),
+        })
+        .collect()
+}
+
+/// A unit's winning bid together with the price it clears at.
+///
+/// `bid` is nested rather than flattened so this type can be encoded in formats like
+/// Postcard that require a known-length representation: `#[serde(flatten)]` makes serde
+/// emit a map of unknown length, which Postcard can't encode at all.
+#[derive(Serialize, PartialEq, Debug)]
+pub struct WinningBidResult<'a> {
+    pub bid: Cow<'a, Bid>,
+    #[serde(serialize_with = "serialize_float")]
+    pub clearing_price: f64,
+}
+
+impl<'a> WinningBidResult<'a> {
+    /// Detaches this result from the auction it was computed from, so it can be
+    /// moved across threads (e.g. from a worker back to a collector) independently
+    /// of the auction's lifetime.
+    pub fn into_owned(self) -> WinningBidResult<'static> {
+        WinningBidResult {
+            bid: Cow::Owned(self.bid.into_owned()),
+            clearing_price: self.clearing_price,
+        }
+    }
 }
 
 struct WinningBid<'a> {
     bid: &'a Bid,
+    revealed_value: f64,
+    adjustment: f64,
     adjusted_bid_value: f64,
+    // The second-highest adjusted bid value seen for this unit so far, if any.
+    second_adjusted_bid_value: Option<f64>,
+}
+
+/// Why a bid didn't win its unit, for diagnostics (see [`get_winning_bids_with_rejections`]).
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BidRejectionReason {
+    /// The auction's site isn't in the config.
+    UnknownSite,
+    /// The bid wasn't revealed, or its revealed value didn't match its commitment.
+    NotRevealed,
+    /// The bid's `ts` falls outside the auction's `[start_ts, end_ts]` window.
+    OutsideAuctionWindow,
+    /// The bid is for a unit that isn't part of the auction.
+    UnitNotInAuction,
+    /// The bidder isn't permitted to bid on the auction's site.
+    BidderNotAllowedOnSite,
+    /// The bidder has no configured adjustment.
+    UnknownBidder,
+    /// The adjusted bid value is below the site's floor.
+    BelowFloor,
+    /// A higher adjusted bid won the unit instead.
+    OutbidByUnit,
+    /// The auction hadn't started yet, or had already ended, at evaluation time.
+    AuctionNotOpen,
+}
+
+/// A rejected bid, reported alongside the winners by [`get_winning_bids_with_rejections`].
+#[derive(Serialize, Debug, PartialEq)]
+pub struct BidRejection<'a> {
+    pub bidder: Cow<'a, str>,
+    pub unit: Cow<'a, str>,
+    pub reason: BidRejectionReason,
+    #[serde(serialize_with = "serialize_float")]
+    pub adjusted_bid_value: f64,
+}
+
+impl<'a> BidRejection<'a> {
+    /// Detaches this rejection from the auction it was computed from, so it can be
+    /// moved across threads independently of the auction's lifetime.
+    pub fn into_owned(self) -> BidRejection<'static> {
+        BidRejection {
+            bidder: Cow::Owned(self.bidder.into_owned()),
+            unit: Cow::Owned(self.unit.into_owned()),
+            reason: self.reason,
+            adjusted_bid_value: self.adjusted_bid_value,
+        }
+    }
 }
 
-fn is_valid_bid(bid: &Bid, auction: &Auction, site_config: &SiteConfig) -> bool {
-    auction.units.contains(&bid.unit) &&      // Bid is for a valid ad unit
-    site_config.bidders.contains(&bid.bidder) // Bidder is permitted to bid on the site
+/// Resolves the usable bid value for a sealed-bid auction's current phase.
+///
+/// Returns `None` for an unrevealed commit-phase bid, or for a reveal-phase bid that's
+/// missing its value/nonce/commitment or whose revealed value doesn't match its commitment.
+fn resolve_bid_value(bid: &Bid, phase: Option<AuctionPhase>) -> Option<f64> {
+    match phase {
+        None => bid.bid,
+        Some(AuctionPhase::Commit) => None,
+        Some(AuctionPhase::Reveal) => {
+            let value = bid.bid?;
+            let nonce = bid.nonce.as_deref()?;
+            let commitment = bid.commitment.as_deref()?;
+
+            let mut hasher = Sha256::new();
+            hasher.update(value.to_le_bytes());
+            hasher.update(nonce.as_bytes());
+            let digest = hex::encode(hasher.finalize());
+
+            if digest.eq_ignore_ascii_case(commitment) {
+                Some(value)
+            } else {
+                None // The revealed value doesn't match the earlier commitment
+            }
+        }
+    }
+}
+
+pub fn get_winning_bids<'a>(auction: &'a Auction, config: &Config) -> Vec<WinningBidResult<'a>> {
+    compute_winning_bids(auction, config, None)
+}
+
+/// Like [`get_winning_bids`], but also returns a report of every bid that didn't win,
+/// with a machine-readable reason, for pipeline debugging and bidder-side troubleshooting.
+pub fn get_winning_bids_with_rejections<'a>(
+    auction: &'a Auction,
+    config: &Config,
+) -> (Vec<WinningBidResult<'a>>, Vec<BidRejection<'a>>) {
+    let mut rejections = Vec::new();
+    let winning_bids = compute_winning_bids(auction, config, Some(&mut rejections));
+    (winning_bids, rejections)
 }
 
-pub fn get_winning_bids<'a>(auction: &'a Auction, config: &Config) -> Vec<&'a Bid> {
+fn compute_winning_bids<'a>(
+    auction: &'a Auction,
+    config: &Config,
+    mut rejections: Option<&mut Vec<BidRejection<'a>>>,
+) -> Vec<WinningBidResult<'a>> {
     let site_config = match config.sites.get(&auction.site) {
         Some(site_config) => site_config,
-        None => return Vec::new(), // The site is unrecognized
+        None => {
+            // The site is unrecognized, so every bid is rejected
+            if let Some(rejections) = rejections.as_deref_mut() {
+                for bid in &auction.bids {
+                    rejections.push(BidRejection {
+                        bidder: Cow::Borrowed(&bid.bidder),
+                        unit: Cow::Borrowed(&bid.unit),
+                        reason: BidRejectionReason::UnknownSite,
+                        adjusted_bid_value: resolve_bid_value(bid, auction.phase).unwrap_or(0.0),
+                    });
+                }
+            }
+            return Vec::new();
+        }
     };
     let site_floor = site_config.floor;
 
     let mut unit_winning_bids: BTreeMap<&String, WinningBid> = BTreeMap::new();
 
     for bid in &auction.bids {
-        if !is_valid_bid(&bid, &auction, &site_config) {
+        let revealed_value = match resolve_bid_value(bid, auction.phase) {
+            Some(value) => value,
+            None => {
+                // Not revealed, or the revealed value doesn't match its commitment
+                if let Some(rejections) = rejections.as_deref_mut() {
+                    rejections.push(BidRejection {
+                        bidder: Cow::Borrowed(&bid.bidder),
+                        unit: Cow::Borrowed(&bid.unit),
+                        reason: BidRejectionReason::NotRevealed,
+                        adjusted_bid_value: 0.0,
+                    });
+                }
+                continue;
+            }
+        };
+
+        let outside_window = bid.ts.is_some_and(|ts| {
+            auction.start_ts.is_some_and(|start_ts| ts < start_ts)
+                || auction.end_ts.is_some_and(|end_ts| ts > end_ts)
+        });
+        if outside_window {
+            if let Some(rejections) = rejections.as_deref_mut() {
+                rejections.push(BidRejection {
+                    bidder: Cow::Borrowed(&bid.bidder),
+                    unit: Cow::Borrowed(&bid.unit),
+                    reason: BidRejectionReason::OutsideAuctionWindow,
+                    adjusted_bid_value: revealed_value,
+                });
+            }
+            continue;
+        }
+
+        if !auction.units.contains(&bid.unit) {
+            if let Some(rejections) = rejections.as_deref_mut() {
+                rejections.push(BidRejection {
+                    bidder: Cow::Borrowed(&bid.bidder),
+                    unit: Cow::Borrowed(&bid.unit),
+                    reason: BidRejectionReason::UnitNotInAuction,
+                    adjusted_bid_value: revealed_value,
+                });
+            }
+            continue;
+        }
+
+        if !site_config.bidders.contains(&bid.bidder) {
+            if let Some(rejections) = rejections.as_deref_mut() {
+                rejections.push(BidRejection {
+                    bidder: Cow::Borrowed(&bid.bidder),
+                    unit: Cow::Borrowed(&bid.unit),
+                    reason: BidRejectionReason::BidderNotAllowedOnSite,
+                    adjusted_bid_value: revealed_value,
+                });
+            }
             continue;
         }
 
         let bidder_adjustment = match config.bidder_adjustments.get(&bid.bidder) {
-            Some(adjustment) => adjustment,
-            None => continue, // Bidder is unknown
+            Some(adjustment) => *adjustment,
+            None => {
+                if let Some(rejections) = rejections.as_deref_mut() {
+                    rejections.push(BidRejection {
+                        bidder: Cow::Borrowed(&bid.bidder),
+                        unit: Cow::Borrowed(&bid.unit),
+                        reason: BidRejectionReason::UnknownBidder,
+                        adjusted_bid_value: revealed_value,
+                    });
+                }
+                continue;
+            }
         };
-        let adjusted_bid_value = bid.bid + bidder_adjustment;
+        let adjusted_bid_value = revealed_value + bidder_adjustment;
 
         if adjusted_bid_value < site_floor {
-            continue; // Bid is invalid since it's below the site's floor
+            if let Some(rejections) = rejections.as_deref_mut() {
+                rejections.push(BidRejection {
+                    bidder: Cow::Borrowed(&bid.bidder),
+                    unit: Cow::Borrowed(&bid.unit),
+                    reason: BidRejectionReason::BelowFloor,
+                    adjusted_bid_value,
+                });
+            }
+            continue;
         }
 
-        let cur_winning_bid = unit_winning_bids.get(&bid.unit);
+        match unit_winning_bids.get_mut(&bid.unit) {
+            None => {
+                unit_winning_bids.insert(
+                    &bid.unit,
+                    WinningBid {
+                        bid,
+                        revealed_value,
+                        adjustment: bidder_adjustment,
+                        adjusted_bid_value,
+                        second_adjusted_bid_value: None,
+                    },
+                );
+            }
+            Some(cur_winning_bid) => {
+                if adjusted_bid_value > cur_winning_bid.adjusted_bid_value {
+                    if let Some(rejections) = rejections.as_deref_mut() {
+                        rejections.push(BidRejection {
+                            bidder: Cow::Borrowed(&cur_winning_bid.bid.bidder),
+                            unit: Cow::Borrowed(&cur_winning_bid.bid.unit),
+                            reason: BidRejectionReason::OutbidByUnit,
+                            adjusted_bid_value: cur_winning_bid.adjusted_bid_value,
+                        });
+                    }
+                    cur_winning_bid.second_adjusted_bid_value = Some(cur_winning_bid.adjusted_bid_value);
+                    cur_winning_bid.bid = bid;
+                    cur_winning_bid.revealed_value = revealed_value;
+                    cur_winning_bid.adjustment = bidder_adjustment;
+                    cur_winning_bid.adjusted_bid_value = adjusted_bid_value;
+                } else {
+                    if let Some(rejections) = rejections.as_deref_mut() {
+                        rejections.push(BidRejection {
+                            bidder: Cow::Borrowed(&bid.bidder),
+                            unit: Cow::Borrowed(&bid.unit),
+                            reason: BidRejectionReason::OutbidByUnit,
+                            adjusted_bid_value,
+                        });
+                    }
 
-        if cur_winning_bid.is_none() // No other bids yet
-            || adjusted_bid_value > cur_winning_bid.unwrap().adjusted_bid_value
-        {
-            unit_winning_bids.insert(
-                &bid.unit,
-                WinningBid {
-                    bid,
-                    adjusted_bid_value,
-                },
-            );
+                    if adjusted_bid_value
+                        > cur_winning_bid
+                            .second_adjusted_bid_value
+                            .unwrap_or(f64::NEG_INFINITY)
+                    {
+                        cur_winning_bid.second_adjusted_bid_value = Some(adjusted_bid_value);
+                    }
+                }
+            }
         }
     }
 
-    // Return the winners' original bid objects
+    // Settle each unit's winner at a price determined by the configured auction type
     unit_winning_bids
         .values()
-        .map(|winning_bid| winning_bid.bid)
+        .map(|winning_bid| {
+            let clearing_price = match config.auction_type {
+                AuctionType::FirstPrice => winning_bid.revealed_value,
+                AuctionType::SecondPrice => match winning_bid.second_adjusted_bid_value {
+                    Some(value) => value.max(site_floor) - winning_bid.adjustment,
+                    None => site_floor,
+                },
+            };
+
+            WinningBidResult {
+                bid: Cow::Borrowed(winning_bid.bid),
+                clearing_price,
+            }
+        })
         .collect()
 }
 
@@ -87,6 +409,18 @@ where
     }
 }
 
+fn serialize_optional_float<S>(value: &Option<f64>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(f) if serializer.is_human_readable() => serialize_float(f, serializer),
+        // Binary formats like Postcard need the `Some` tag and a real f64 to decode the field
+        Some(f) => serializer.serialize_some(f),
+        None => serializer.serialize_none(),
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// Tests
 ///////////////////////////////////////////////////////////////////////////////
@@ -134,15 +468,27 @@ mod tests {
         assert_eq!(
             get_winning_bids(&auction, &get_test_config()),
             vec![
-                &Bid {
-                    bidder: "AUCT".to_string(),
-                    unit: "banner".to_string(),
-                    bid: 35.0,
+                WinningBidResult {
+                    bid: Cow::Borrowed(&Bid {
+                        bidder: "AUCT".to_string(),
+                        unit: "banner".to_string(),
+                        bid: Some(35.0),
+                        commitment: None,
+                        nonce: None,
+                        ts: None,
+                    }),
+                    clearing_price: 35.0,
                 },
-                &Bid {
-                    bidder: "BIDD".to_string(),
-                    unit: "sidebar".to_string(),
-                    bid: 60.0,
+                WinningBidResult {
+                    bid: Cow::Borrowed(&Bid {
+                        bidder: "BIDD".to_string(),
+                        unit: "sidebar".to_string(),
+                        bid: Some(60.0),
+                        commitment: None,
+                        nonce: None,
+                        ts: None,
+                    }),
+                    clearing_price: 60.0,
                 },
             ]
         );
@@ -174,7 +520,7 @@ mod tests {
             }"#,
         );
 
-        let expected: Vec<&Bid> = vec![];
+        let expected: Vec<WinningBidResult> = vec![];
 
         assert_eq!(get_winning_bids(&auction, &get_test_config()), expected);
     }
@@ -205,7 +551,7 @@ mod tests {
             }"#,
         );
 
-        let expected: Vec<&Bid> = vec![];
+        let expected: Vec<WinningBidResult> = vec![];
 
         assert_eq!(get_winning_bids(&auction, &get_test_config()), expected);
     }
@@ -238,11 +584,17 @@ mod tests {
 
         assert_eq!(
             get_winning_bids(&auction, &get_test_config()),
-            vec![&Bid {
-                bidder: "AUCT".to_string(),
-                unit: "sidebar".to_string(),
-                bid: 55.0,
-            },]
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "AUCT".to_string(),
+                    unit: "sidebar".to_string(),
+                    bid: Some(55.0),
+                    commitment: None,
+                    nonce: None,
+                    ts: None,
+                }),
+                clearing_price: 55.0,
+            }]
         );
     }
 
@@ -274,11 +626,17 @@ mod tests {
 
         assert_eq!(
             get_winning_bids(&auction, &get_test_config()),
-            vec![&Bid {
-                bidder: "AUCT".to_string(),
-                unit: "sidebar".to_string(),
-                bid: 55.0,
-            },]
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "AUCT".to_string(),
+                    unit: "sidebar".to_string(),
+                    bid: Some(55.0),
+                    commitment: None,
+                    nonce: None,
+                    ts: None,
+                }),
+                clearing_price: 55.0,
+            }]
         );
     }
 
@@ -310,11 +668,17 @@ mod tests {
 
         assert_eq!(
             get_winning_bids(&auction, &get_test_config()),
-            vec![&Bid {
-                bidder: "AUCT".to_string(),
-                unit: "banner".to_string(),
-                bid: 35.0,
-            },]
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "AUCT".to_string(),
+                    unit: "banner".to_string(),
+                    bid: Some(35.0),
+                    commitment: None,
+                    nonce: None,
+                    ts: None,
+                }),
+                clearing_price: 35.0,
+            }]
         );
     }
 
@@ -346,11 +710,17 @@ mod tests {
 
         assert_eq!(
             get_winning_bids(&auction, &get_test_config()),
-            vec![&Bid {
-                bidder: "BIDD".to_string(),
-                unit: "sidebar".to_string(),
-                bid: 60.0,
-            },]
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "BIDD".to_string(),
+                    unit: "sidebar".to_string(),
+                    bid: Some(60.0),
+                    commitment: None,
+                    nonce: None,
+                    ts: None,
+                }),
+                clearing_price: 60.0,
+            }]
         );
     }
 
@@ -382,11 +752,17 @@ mod tests {
 
         assert_eq!(
             get_winning_bids(&auction, &get_test_config()),
-            vec![&Bid {
-                bidder: "BIDD".to_string(),
-                unit: "sidebar".to_string(),
-                bid: 60.0,
-            },]
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "BIDD".to_string(),
+                    unit: "sidebar".to_string(),
+                    bid: Some(60.0),
+                    commitment: None,
+                    nonce: None,
+                    ts: None,
+                }),
+                clearing_price: 60.0,
+            }]
         );
     }
 
@@ -413,11 +789,17 @@ mod tests {
 
         assert_eq!(
             get_winning_bids(&auction, &get_test_config()),
-            vec![&Bid {
-                bidder: "AUCT".to_string(),
-                unit: "sidebar".to_string(),
-                bid: 61.0,
-            },]
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "AUCT".to_string(),
+                    unit: "sidebar".to_string(),
+                    bid: Some(61.0),
+                    commitment: None,
+                    nonce: None,
+                    ts: None,
+                }),
+                clearing_price: 61.0,
+            }]
         );
     }
 
@@ -444,11 +826,17 @@ mod tests {
 
         assert_eq!(
             get_winning_bids(&auction, &get_test_config()),
-            vec![&Bid {
-                bidder: "AUCT".to_string(),
-                unit: "sidebar".to_string(),
-                bid: 60.0625,
-            },]
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "AUCT".to_string(),
+                    unit: "sidebar".to_string(),
+                    bid: Some(60.0625),
+                    commitment: None,
+                    nonce: None,
+                    ts: None,
+                }),
+                clearing_price: 60.0625,
+            }]
         );
     }
 
@@ -475,11 +863,17 @@ mod tests {
 
         assert_eq!(
             get_winning_bids(&auction, &get_test_config()),
-            vec![&Bid {
-                bidder: "BIDD".to_string(),
-                unit: "sidebar".to_string(),
-                bid: 60.0,
-            },]
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "BIDD".to_string(),
+                    unit: "sidebar".to_string(),
+                    bid: Some(60.0),
+                    commitment: None,
+                    nonce: None,
+                    ts: None,
+                }),
+                clearing_price: 60.0,
+            }]
         );
     }
 
@@ -511,11 +905,17 @@ mod tests {
 
         assert_eq!(
             get_winning_bids(&auction, &get_test_config()),
-            vec![&Bid {
-                bidder: "BIDD".to_string(),
-                unit: "sidebar".to_string(),
-                bid: 60.0625,
-            },]
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "BIDD".to_string(),
+                    unit: "sidebar".to_string(),
+                    bid: Some(60.0625),
+                    commitment: None,
+                    nonce: None,
+                    ts: None,
+                }),
+                clearing_price: 60.0625,
+            }]
         );
     }
 
@@ -529,8 +929,763 @@ mod tests {
             }"#,
         );
 
-        let expected: Vec<&Bid> = vec![];
+        let expected: Vec<WinningBidResult> = vec![];
+
+        assert_eq!(get_winning_bids(&auction, &get_test_config()), expected);
+    }
+
+    #[test]
+    fn test_second_price_clears_at_runner_up_bid() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["sidebar"],
+              "bids": [
+                {
+                  "bidder": "BIDD",
+                  "unit": "sidebar",
+                  "bid": 35
+                },
+                {
+                  "bidder": "BIDD",
+                  "unit": "sidebar",
+                  "bid": 60.0625
+                },
+                {
+                  "bidder": "BIDD",
+                  "unit": "sidebar",
+                  "bid": 60
+                }
+              ]
+            }"#,
+        );
+
+        let config = Config {
+            auction_type: AuctionType::SecondPrice,
+            ..get_test_config()
+        };
+
+        assert_eq!(
+            get_winning_bids(&auction, &config),
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "BIDD".to_string(),
+                    unit: "sidebar".to_string(),
+                    bid: Some(60.0625),
+                    commitment: None,
+                    nonce: None,
+                    ts: None,
+                }),
+                clearing_price: 60.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_second_price_clears_at_site_floor_with_one_bid() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["banner"],
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "bid": 35
+                }
+              ]
+            }"#,
+        );
+
+        let config = Config {
+            auction_type: AuctionType::SecondPrice,
+            ..get_test_config()
+        };
+        let site_floor = config.sites.get("houseofcheese.com").unwrap().floor;
+
+        assert_eq!(
+            get_winning_bids(&auction, &config),
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "AUCT".to_string(),
+                    unit: "banner".to_string(),
+                    bid: Some(35.0),
+                    commitment: None,
+                    nonce: None,
+                    ts: None,
+                }),
+                clearing_price: site_floor,
+            }]
+        );
+    }
+
+    // The clearing price is the runner-up's adjusted value translated back to the
+    // *winner's* pre-adjustment scale, not the runner-up's, so the settlement still
+    // makes sense when every bidder carries a different adjustment.
+    #[test]
+    fn test_second_price_clears_at_runner_up_bid_with_different_bidder_adjustments() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["sidebar"],
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "sidebar",
+                  "bid": 200
+                },
+                {
+                  "bidder": "BIDD",
+                  "unit": "sidebar",
+                  "bid": 150
+                }
+              ]
+            }"#,
+        );
+
+        let config = Config {
+            auction_type: AuctionType::SecondPrice,
+            ..get_test_config()
+        };
+        let auct_adjustment = *config.bidder_adjustments.get("AUCT").unwrap();
+        let bidd_adjustment = *config.bidder_adjustments.get("BIDD").unwrap();
+        let site_floor = config.sites.get("houseofcheese.com").unwrap().floor;
+
+        assert_eq!(
+            get_winning_bids(&auction, &config),
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "AUCT".to_string(),
+                    unit: "sidebar".to_string(),
+                    bid: Some(200.0),
+                    commitment: None,
+                    nonce: None,
+                    ts: None,
+                }),
+                clearing_price: (150.0 + bidd_adjustment).max(site_floor) - auct_adjustment,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_reveal_phase_with_matching_commitment_wins() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["banner"],
+              "phase": "reveal",
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "bid": 35,
+                  "nonce": "nonce-auct",
+                  "commitment": "15b254942d755d306b802c3b4c53922a21c795a2cc63eaf6f8299f0665149cb7"
+                }
+              ]
+            }"#,
+        );
+
+        assert_eq!(
+            get_winning_bids(&auction, &get_test_config()),
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "AUCT".to_string(),
+                    unit: "banner".to_string(),
+                    bid: Some(35.0),
+                    commitment: Some(
+                        "15b254942d755d306b802c3b4c53922a21c795a2cc63eaf6f8299f0665149cb7".to_string()
+                    ),
+                    nonce: Some("nonce-auct".to_string()),
+                    ts: None,
+                }),
+                clearing_price: 35.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_reveal_phase_with_uppercase_commitment_wins() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["banner"],
+              "phase": "reveal",
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "bid": 35,
+                  "nonce": "nonce-auct",
+                  "commitment": "15B254942D755D306B802C3B4C53922A21C795A2CC63EAF6F8299F0665149CB7"
+                }
+              ]
+            }"#,
+        );
+
+        assert_eq!(
+            get_winning_bids(&auction, &get_test_config()),
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "AUCT".to_string(),
+                    unit: "banner".to_string(),
+                    bid: Some(35.0),
+                    commitment: Some(
+                        "15B254942D755D306B802C3B4C53922A21C795A2CC63EAF6F8299F0665149CB7".to_string()
+                    ),
+                    nonce: Some("nonce-auct".to_string()),
+                    ts: None,
+                }),
+                clearing_price: 35.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_reveal_phase_drops_bid_with_mismatched_commitment() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["banner"],
+              "phase": "reveal",
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "bid": 35,
+                  "nonce": "wrong-nonce",
+                  "commitment": "15b254942d755d306b802c3b4c53922a21c795a2cc63eaf6f8299f0665149cb7"
+                }
+              ]
+            }"#,
+        );
+
+        let expected: Vec<WinningBidResult> = vec![];
+
+        assert_eq!(get_winning_bids(&auction, &get_test_config()), expected);
+    }
+
+    #[test]
+    fn test_reveal_phase_drops_unrevealed_bid() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["banner"],
+              "phase": "reveal",
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "commitment": "15b254942d755d306b802c3b4c53922a21c795a2cc63eaf6f8299f0665149cb7"
+                }
+              ]
+            }"#,
+        );
+
+        let expected: Vec<WinningBidResult> = vec![];
 
         assert_eq!(get_winning_bids(&auction, &get_test_config()), expected);
     }
+
+    #[test]
+    fn test_commit_phase_produces_no_winners() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["banner"],
+              "phase": "commit",
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "commitment": "15b254942d755d306b802c3b4c53922a21c795a2cc63eaf6f8299f0665149cb7"
+                }
+              ]
+            }"#,
+        );
+
+        let expected: Vec<WinningBidResult> = vec![];
+
+        assert_eq!(get_winning_bids(&auction, &get_test_config()), expected);
+    }
+
+    #[test]
+    fn test_reveal_phase_duplicate_commitments_resolve_to_highest_value() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["sidebar"],
+              "phase": "reveal",
+              "bids": [
+                {
+                  "bidder": "BIDD",
+                  "unit": "sidebar",
+                  "bid": 60,
+                  "nonce": "nonce-bidd",
+                  "commitment": "21e91563f6ee1c9036f35b34a1342bb348bbd48c314bde3c42fa230474c6bfed"
+                },
+                {
+                  "bidder": "BIDD",
+                  "unit": "sidebar",
+                  "bid": 35,
+                  "nonce": "nonce-auct",
+                  "commitment": "15b254942d755d306b802c3b4c53922a21c795a2cc63eaf6f8299f0665149cb7"
+                }
+              ]
+            }"#,
+        );
+
+        assert_eq!(
+            get_winning_bids(&auction, &get_test_config()),
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "BIDD".to_string(),
+                    unit: "sidebar".to_string(),
+                    bid: Some(60.0),
+                    commitment: Some(
+                        "21e91563f6ee1c9036f35b34a1342bb348bbd48c314bde3c42fa230474c6bfed".to_string()
+                    ),
+                    nonce: Some("nonce-bidd".to_string()),
+                    ts: None,
+                }),
+                clearing_price: 60.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_rejections_report_unknown_site() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "unknown.com",
+              "units": ["banner"],
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "bid": 35
+                }
+              ]
+            }"#,
+        );
+
+        let (winners, rejections): (Vec<WinningBidResult>, _) =
+            get_winning_bids_with_rejections(&auction, &get_test_config());
+
+        assert_eq!(winners, vec![]);
+        assert_eq!(
+            rejections,
+            vec![BidRejection {
+                bidder: Cow::Borrowed("AUCT"),
+                unit: Cow::Borrowed("banner"),
+                reason: BidRejectionReason::UnknownSite,
+                adjusted_bid_value: 35.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_rejections_report_every_drop_reason() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["sidebar"],
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "bid": 35
+                },
+                {
+                  "bidder": "WHO?",
+                  "unit": "sidebar",
+                  "bid": 35
+                },
+                {
+                  "bidder": "AUCT",
+                  "unit": "sidebar",
+                  "bid": 32
+                },
+                {
+                  "bidder": "BIDD",
+                  "unit": "sidebar",
+                  "bid": 55
+                },
+                {
+                  "bidder": "AUCT",
+                  "unit": "sidebar",
+                  "bid": 60
+                }
+              ]
+            }"#,
+        );
+
+        let config = get_test_config();
+        let auct_adjustment = *config.bidder_adjustments.get("AUCT").unwrap();
+        let bidd_adjustment = *config.bidder_adjustments.get("BIDD").unwrap();
+
+        let (winners, rejections) = get_winning_bids_with_rejections(&auction, &config);
+
+        assert_eq!(
+            winners,
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "AUCT".to_string(),
+                    unit: "sidebar".to_string(),
+                    bid: Some(60.0),
+                    commitment: None,
+                    nonce: None,
+                    ts: None,
+                }),
+                clearing_price: 60.0,
+            }]
+        );
+        assert_eq!(
+            rejections,
+            vec![
+                BidRejection {
+                    bidder: Cow::Borrowed("AUCT"),
+                    unit: Cow::Borrowed("banner"),
+                    reason: BidRejectionReason::UnitNotInAuction,
+                    adjusted_bid_value: 35.0,
+                },
+                BidRejection {
+                    bidder: Cow::Borrowed("WHO?"),
+                    unit: Cow::Borrowed("sidebar"),
+                    reason: BidRejectionReason::UnknownBidder,
+                    adjusted_bid_value: 35.0,
+                },
+                BidRejection {
+                    bidder: Cow::Borrowed("AUCT"),
+                    unit: Cow::Borrowed("sidebar"),
+                    reason: BidRejectionReason::BelowFloor,
+                    adjusted_bid_value: 32.0 + auct_adjustment,
+                },
+                BidRejection {
+                    bidder: Cow::Borrowed("BIDD"),
+                    unit: Cow::Borrowed("sidebar"),
+                    reason: BidRejectionReason::OutbidByUnit,
+                    adjusted_bid_value: 55.0 + bidd_adjustment,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rejections_report_bidder_not_allowed_on_site() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "auct-only.com",
+              "units": ["banner"],
+              "bids": [
+                {
+                  "bidder": "BIDD",
+                  "unit": "banner",
+                  "bid": 35
+                }
+              ]
+            }"#,
+        );
+
+        let (winners, rejections): (Vec<WinningBidResult>, _) =
+            get_winning_bids_with_rejections(&auction, &get_test_config());
+
+        assert_eq!(winners, vec![]);
+        assert_eq!(
+            rejections,
+            vec![BidRejection {
+                bidder: Cow::Borrowed("BIDD"),
+                unit: Cow::Borrowed("banner"),
+                reason: BidRejectionReason::BidderNotAllowedOnSite,
+                adjusted_bid_value: 35.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_rejections_report_unrevealed_bid_in_reveal_phase() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["banner"],
+              "phase": "reveal",
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "commitment": "15b254942d755d306b802c3b4c53922a21c795a2cc63eaf6f8299f0665149cb7"
+                }
+              ]
+            }"#,
+        );
+
+        let (winners, rejections): (Vec<WinningBidResult>, _) =
+            get_winning_bids_with_rejections(&auction, &get_test_config());
+
+        assert_eq!(winners, vec![]);
+        assert_eq!(
+            rejections,
+            vec![BidRejection {
+                bidder: Cow::Borrowed("AUCT"),
+                unit: Cow::Borrowed("banner"),
+                reason: BidRejectionReason::NotRevealed,
+                adjusted_bid_value: 0.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_bid_before_auction_start_is_rejected() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["banner"],
+              "start_ts": 1000,
+              "end_ts": 2000,
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "bid": 35,
+                  "ts": 999
+                }
+              ]
+            }"#,
+        );
+
+        let expected: Vec<WinningBidResult> = vec![];
+
+        assert_eq!(get_winning_bids(&auction, &get_test_config()), expected);
+    }
+
+    #[test]
+    fn test_bid_after_auction_end_is_rejected() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["banner"],
+              "start_ts": 1000,
+              "end_ts": 2000,
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "bid": 35,
+                  "ts": 2001
+                }
+              ]
+            }"#,
+        );
+
+        let expected: Vec<WinningBidResult> = vec![];
+
+        assert_eq!(get_winning_bids(&auction, &get_test_config()), expected);
+    }
+
+    #[test]
+    fn test_bid_within_auction_window_wins() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["banner"],
+              "start_ts": 1000,
+              "end_ts": 2000,
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "bid": 35,
+                  "ts": 1500
+                }
+              ]
+            }"#,
+        );
+
+        assert_eq!(
+            get_winning_bids(&auction, &get_test_config()),
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "AUCT".to_string(),
+                    unit: "banner".to_string(),
+                    bid: Some(35.0),
+                    commitment: None,
+                    nonce: None,
+                    ts: Some(1500),
+                }),
+                clearing_price: 35.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_bid_with_no_ts_is_not_checked_against_auction_window() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["banner"],
+              "start_ts": 1000,
+              "end_ts": 2000,
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "bid": 35
+                }
+              ]
+            }"#,
+        );
+
+        assert_eq!(
+            get_winning_bids(&auction, &get_test_config()),
+            vec![WinningBidResult {
+                bid: Cow::Borrowed(&Bid {
+                    bidder: "AUCT".to_string(),
+                    unit: "banner".to_string(),
+                    bid: Some(35.0),
+                    commitment: None,
+                    nonce: None,
+                    ts: None,
+                }),
+                clearing_price: 35.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_rejections_report_bid_outside_auction_window() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["banner"],
+              "start_ts": 1000,
+              "end_ts": 2000,
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "bid": 35,
+                  "ts": 2001
+                }
+              ]
+            }"#,
+        );
+
+        let (winners, rejections): (Vec<WinningBidResult>, _) =
+            get_winning_bids_with_rejections(&auction, &get_test_config());
+
+        assert_eq!(winners, vec![]);
+        assert_eq!(
+            rejections,
+            vec![BidRejection {
+                bidder: Cow::Borrowed("AUCT"),
+                unit: Cow::Borrowed("banner"),
+                reason: BidRejectionReason::OutsideAuctionWindow,
+                adjusted_bid_value: 35.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_auction_status_before_start() {
+        let auction = auction_from_json(
+            r#"{"site": "houseofcheese.com", "units": [], "bids": [], "start_ts": 1000, "end_ts": 2000}"#,
+        );
+
+        assert_eq!(auction_status(&auction, 999), AuctionStatus::NotStarted);
+    }
+
+    #[test]
+    fn test_auction_status_within_window() {
+        let auction = auction_from_json(
+            r#"{"site": "houseofcheese.com", "units": [], "bids": [], "start_ts": 1000, "end_ts": 2000}"#,
+        );
+
+        assert_eq!(auction_status(&auction, 1500), AuctionStatus::Open);
+    }
+
+    #[test]
+    fn test_auction_status_after_end() {
+        let auction = auction_from_json(
+            r#"{"site": "houseofcheese.com", "units": [], "bids": [], "start_ts": 1000, "end_ts": 2000}"#,
+        );
+
+        assert_eq!(auction_status(&auction, 2001), AuctionStatus::Ended);
+    }
+
+    #[test]
+    fn test_auction_status_is_open_with_no_bounds() {
+        let auction = auction_from_json(
+            r#"{"site": "houseofcheese.com", "units": [], "bids": []}"#,
+        );
+
+        assert_eq!(auction_status(&auction, 0), AuctionStatus::Open);
+    }
+
+    #[test]
+    fn test_reject_closed_auction_reports_every_bid() {
+        let auction = auction_from_json(
+            r#"{
+              "site": "houseofcheese.com",
+              "units": ["banner"],
+              "start_ts": 1000,
+              "end_ts": 2000,
+              "bids": [
+                {
+                  "bidder": "AUCT",
+                  "unit": "banner",
+                  "bid": 35
+                }
+              ]
+            }"#,
+        );
+
+        assert_eq!(
+            reject_closed_auction(&auction),
+            vec![BidRejection {
+                bidder: Cow::Borrowed("AUCT"),
+                unit: Cow::Borrowed("banner"),
+                reason: BidRejectionReason::AuctionNotOpen,
+                adjusted_bid_value: 35.0,
+            }]
+        );
+    }
+
+    // `Bid`'s optional fields must not use `skip_serializing_if`: MessagePack and
+    // Postcard decode struct fields positionally, so omitting an absent field shifts
+    // every field after it in the stream. A `ts` present alongside an absent
+    // `commitment`/`nonce` (a mix chunk0-4 and chunk0-6 can both produce) is exactly
+    // the case that corrupted the stream before this field encoded its own presence.
+    #[test]
+    fn test_bid_roundtrips_through_messagepack_with_mixed_optional_fields() {
+        let bid = Bid {
+            bidder: "BIDD".to_string(),
+            unit: "banner".to_string(),
+            bid: Some(35.0),
+            commitment: None,
+            nonce: None,
+            ts: Some(123),
+        };
+
+        let bytes = rmp_serde::to_vec(&bid).unwrap();
+        let decoded: Bid = rmp_serde::from_slice(&bytes).unwrap();
+
+        assert_eq!(decoded, bid);
+    }
+
+    #[test]
+    fn test_bid_roundtrips_through_postcard_with_mixed_optional_fields() {
+        let bid = Bid {
+            bidder: "BIDD".to_string(),
+            unit: "banner".to_string(),
+            bid: Some(35.0),
+            commitment: None,
+            nonce: None,
+            ts: Some(123),
+        };
+
+        let bytes = postcard::to_allocvec(&bid).unwrap();
+        let decoded: Bid = postcard::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded, bid);
+    }
 }
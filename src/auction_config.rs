@@ -24,6 +24,20 @@ struct JsonBidderConfig {
 struct JsonConfig {
     sites: Vec<JsonSiteConfig>,
     bidders: Vec<JsonBidderConfig>,
+    #[serde(default)]
+    auction_type: AuctionType,
+}
+
+/// The clearing-price rule used to settle each unit's auction.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionType {
+    /// The winner pays their own bid, before their adjustment is applied.
+    #[default]
+    FirstPrice,
+    /// The winner pays the higher of the second-highest adjusted bid and the site floor,
+    /// minus the winner's own adjustment. With no second bid, the winner pays the site floor.
+    SecondPrice,
 }
 
 #[derive(Debug)]
@@ -36,6 +50,7 @@ pub struct SiteConfig {
 pub struct Config {
     pub sites: HashMap<String, SiteConfig>,
     pub bidder_adjustments: HashMap<String, f64>,
+    pub auction_type: AuctionType,
 }
 
 fn load_json_config<P: AsRef<Path>>(path: P) -> Result<JsonConfig, Box<dyn Error>> {
@@ -69,5 +84,6 @@ pub fn get_config<P: AsRef<Path>>(path: P) -> Config {
     Config {
         sites,
         bidder_adjustments,
+        auction_type: json_config.auction_type,
     }
 }
@@ -1,18 +1,147 @@
 mod auction;
 mod auction_config;
 
+use auction_config::Config;
+
 use serde::de::{Deserializer, SeqAccess, Visitor};
-use serde::ser::{SerializeSeq, Serializer};
+use serde::ser::SerializeSeq;
+use serde::{Serialize, Serializer};
 
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
 use std::fmt;
-use std::io::{self, BufReader, BufWriter};
-use std::sync::mpsc;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 const CONFIG_PATH: &str = "/auction/config.json";
 
+// Bounds how far the decoder can run ahead of the worker pool and how far the
+// worker pool can run ahead of the collector, so the channels stay small on huge
+// inputs. The collector's reorder buffer and the non-JSON output paths are not
+// bounded by these (see `score_auctions` and `run`).
+const JOB_CHANNEL_CAPACITY: usize = 256;
+const RESULT_CHANNEL_CAPACITY: usize = 256;
+
+/// The wire format used to read auctions from stdin and write results to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WireFormat {
+    Json,
+    MessagePack,
+    Postcard,
+}
+
+impl WireFormat {
+    fn parse(s: &str) -> Option<WireFormat> {
+        match s.to_lowercase().as_str() {
+            "json" => Some(WireFormat::Json),
+            "msgpack" | "messagepack" => Some(WireFormat::MessagePack),
+            "postcard" => Some(WireFormat::Postcard),
+            _ => None,
+        }
+    }
+
+    /// Reads the format from a `--format <name>` / `--format=<name>` CLI argument,
+    /// falling back to the `AUCTION_FORMAT` environment variable, defaulting to JSON.
+    fn from_env() -> WireFormat {
+        Self::resolve(env::args().skip(1), |name| env::var(name).ok())
+    }
+
+    /// The argument/env-lookup-agnostic core of [`WireFormat::from_env`], so CLI-flag
+    /// and env-var precedence can be exercised without touching the process environment.
+    fn resolve(args: impl Iterator<Item = String>, get_env: impl Fn(&str) -> Option<String>) -> WireFormat {
+        let mut args = args;
+
+        while let Some(arg) = args.next() {
+            let value = if arg == "--format" {
+                args.next()
+            } else {
+                arg.strip_prefix("--format=").map(str::to_string)
+            };
+
+            if let Some(format) = value.and_then(|v| WireFormat::parse(&v)) {
+                return format;
+            }
+        }
+
+        get_env("AUCTION_FORMAT").and_then(|value| WireFormat::parse(&value)).unwrap_or(WireFormat::Json)
+    }
+}
+
+/// Reads the worker pool size from the `AUCTION_WORKERS` environment variable,
+/// defaulting to the number of available CPUs.
+fn worker_count_from_env() -> usize {
+    resolve_worker_count(|name| env::var(name).ok(), || {
+        thread::available_parallelism().map(|count| count.get()).unwrap_or(1)
+    })
+}
+
+/// The env-lookup-agnostic core of [`worker_count_from_env`].
+fn resolve_worker_count(get_env: impl Fn(&str) -> Option<String>, default_count: impl FnOnce() -> usize) -> usize {
+    get_env("AUCTION_WORKERS")
+        .and_then(|value| value.parse().ok())
+        .filter(|&count| count > 0)
+        .unwrap_or_else(default_count)
+}
+
+/// Reads the `--explain` CLI flag or the `AUCTION_EXPLAIN` environment variable,
+/// which switches the output from winners-only to a per-auction report that also
+/// lists every rejected bid along with the reason it didn't win.
+fn explain_from_env() -> bool {
+    resolve_explain(env::args().skip(1), |name| env::var(name).ok())
+}
+
+/// The argument/env-lookup-agnostic core of [`explain_from_env`].
+fn resolve_explain(mut args: impl Iterator<Item = String>, get_env: impl Fn(&str) -> Option<String>) -> bool {
+    args.any(|arg| arg == "--explain")
+        || get_env("AUCTION_EXPLAIN").map(|value| value == "1").unwrap_or(false)
+}
+
+/// Reads the "now" timestamp used to classify each auction's lifecycle state, from the
+/// `--now <unix_ts>` / `--now=<unix_ts>` CLI argument or the `AUCTION_NOW` environment
+/// variable, falling back to the current system time. Pinning this lets recorded bid
+/// streams be replayed with the temporal eligibility they had at some point in the past.
+fn now_ts_from_env() -> i64 {
+    resolve_now_ts(env::args().skip(1), |name| env::var(name).ok(), || {
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs() as i64).unwrap_or(0)
+    })
+}
+
+/// The argument/env-lookup-agnostic core of [`now_ts_from_env`].
+fn resolve_now_ts(
+    args: impl Iterator<Item = String>,
+    get_env: impl Fn(&str) -> Option<String>,
+    default_now_ts: impl FnOnce() -> i64,
+) -> i64 {
+    let mut args = args;
+
+    while let Some(arg) = args.next() {
+        let value = if arg == "--now" {
+            args.next()
+        } else {
+            arg.strip_prefix("--now=").map(str::to_string)
+        };
+
+        if let Some(now_ts) = value.and_then(|v| v.parse().ok()) {
+            return now_ts;
+        }
+    }
+
+    get_env("AUCTION_NOW").and_then(|value| value.parse().ok()).unwrap_or_else(default_now_ts)
+}
+
+/// An auction's winners together with every bid that didn't win, for `--explain` mode.
+#[derive(Serialize)]
+struct AuctionReport<'a> {
+    winners: Vec<auction::WinningBidResult<'a>>,
+    rejections: Vec<auction::BidRejection<'a>>,
+}
+
 struct AuctionProcessor {
-    sender: mpsc::Sender<auction::Auction>,
+    sender: mpsc::SyncSender<(usize, auction::Auction)>,
+    next_index: usize,
 }
 
 impl<'s> Visitor<'s> for AuctionProcessor {
@@ -22,38 +151,340 @@ impl<'s> Visitor<'s> for AuctionProcessor {
         formatter.write_str("an array of auction objects")
     }
 
-    fn visit_seq<SA>(self, mut seq: SA) -> Result<(), SA::Error>
+    fn visit_seq<SA>(mut self, mut seq: SA) -> Result<(), SA::Error>
     where
         SA: SeqAccess<'s>,
     {
-        // Deserialize the auctions and send them to the main thread for processing
+        // Tag each auction with its position in the input so the collector can
+        // restore input order after the worker pool processes auctions out of order.
         while let Some(auction) = seq.next_element::<auction::Auction>()? {
-            self.sender.send(auction).unwrap();
+            self.sender.send((self.next_index, auction)).unwrap();
+            self.next_index += 1;
         }
         Ok(())
     }
 }
 
-fn main() -> serde_json::Result<()> {
-    let (sender, receiver) = mpsc::channel();
+/// Decodes auctions from `reader` in the given wire format and streams them,
+/// tagged with their input position, to `sender`.
+fn read_auctions<R: Read>(
+    format: WireFormat,
+    mut reader: R,
+    sender: mpsc::SyncSender<(usize, auction::Auction)>,
+) {
+    let auction_processor = AuctionProcessor { sender, next_index: 0 };
+
+    match format {
+        WireFormat::Json => {
+            let mut deserializer = serde_json::Deserializer::from_reader(reader);
+            deserializer.deserialize_seq(auction_processor).unwrap();
+        }
+        WireFormat::MessagePack => {
+            let mut deserializer = rmp_serde::Deserializer::new(reader);
+            deserializer.deserialize_seq(auction_processor).unwrap();
+        }
+        WireFormat::Postcard => {
+            // Postcard decodes from an in-memory byte slice rather than a stream,
+            // so the input has to be buffered fully before it can be deserialized.
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).unwrap();
+            let mut deserializer = postcard::Deserializer::from_bytes(&bytes);
+            deserializer.deserialize_seq(auction_processor).unwrap();
+        }
+    }
+}
+
+/// Scores auctions from `job_receiver` against `config` using a bounded pool of
+/// worker threads, and passes each auction's `score` result to `on_result`, in
+/// input order, as soon as it's available.
+///
+/// Workers pull `(index, Auction)` jobs and push `(index, result)` back to this
+/// thread, which buffers out-of-order results in a `BTreeMap` and drains it by
+/// calling `on_result` as soon as the next expected index arrives. The buffer holds
+/// every result that finished ahead of a slower, earlier auction; its size is not
+/// bounded by the channel capacities. `on_result`
+/// runs on this thread rather than the workers, so a writer can be driven
+/// directly from it without needing to be `Send`. If `on_result` returns an
+/// error, the remaining results are still drained from the channel so the
+/// workers never block on a full result queue, and the first error is
+/// returned once every worker has finished.
+fn score_auctions<T, F, E>(
+    job_receiver: mpsc::Receiver<(usize, auction::Auction)>,
+    config: Config,
+    worker_count: usize,
+    score: F,
+    mut on_result: impl FnMut(T) -> Result<(), E>,
+) -> Result<(), E>
+where
+    F: Fn(&auction::Auction, &Config) -> T + Copy + Send + 'static,
+    T: Send + 'static,
+{
+    let config = Arc::new(config);
+    let job_receiver = Arc::new(Mutex::new(job_receiver));
+    let (result_sender, result_receiver) = mpsc::sync_channel(RESULT_CHANNEL_CAPACITY);
+
+    let workers: Vec<_> = (0..worker_count)
+        .map(|_| {
+            let job_receiver = Arc::clone(&job_receiver);
+            let config = Arc::clone(&config);
+            let result_sender = result_sender.clone();
+
+            thread::spawn(move || loop {
+                let job = job_receiver.lock().unwrap().recv();
+                let (index, auction) = match job {
+                    Ok(job) => job,
+                    Err(_) => break, // The decoder thread finished and dropped its sender
+                };
+
+                let result = score(&auction, &config);
+                result_sender.send((index, result)).unwrap();
+            })
+        })
+        .collect();
+    drop(result_sender);
+
+    let mut pending = BTreeMap::new();
+    let mut next_index = 0;
+    let mut first_error = None;
+
+    for (index, result) in result_receiver {
+        pending.insert(index, result);
+
+        while let Some(result) = pending.remove(&next_index) {
+            next_index += 1;
+            if first_error.is_none() {
+                first_error = on_result(result).err();
+            }
+        }
+    }
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    match first_error {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// Scores every auction from `job_receiver` with `score` and encodes the ordered
+/// results to `writer` in the given wire format.
+///
+/// For JSON, each result is streamed straight to `writer` as soon as it's computed,
+/// via a manual [`SerializeSeq`], so peak memory stays flat regardless of input size.
+/// MessagePack and Postcard both encode a sequence's length up front, so for those
+/// formats the results still have to be buffered into a `Vec` before the single
+/// encode call that writes them out.
+fn run<T, F>(
+    format: WireFormat,
+    job_receiver: mpsc::Receiver<(usize, auction::Auction)>,
+    config: Config,
+    worker_count: usize,
+    mut writer: impl Write,
+    score: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: Fn(&auction::Auction, &Config) -> T + Copy + Send + 'static,
+    T: Serialize + Send + 'static,
+{
+    match format {
+        WireFormat::Json => {
+            let mut serializer = serde_json::Serializer::new(writer);
+            let mut seq = serializer.serialize_seq(None)?;
+            score_auctions(job_receiver, config, worker_count, score, |result| {
+                seq.serialize_element(&result)
+            })?;
+            seq.end()?;
+        }
+        WireFormat::MessagePack => {
+            let mut results = Vec::new();
+            score_auctions::<_, _, Box<dyn Error>>(
+                job_receiver,
+                config,
+                worker_count,
+                score,
+                |result| {
+                    results.push(result);
+                    Ok(())
+                },
+            )?;
+            results.serialize(&mut rmp_serde::Serializer::new(&mut writer))?;
+        }
+        WireFormat::Postcard => {
+            let mut results = Vec::new();
+            score_auctions::<_, _, Box<dyn Error>>(
+                job_receiver,
+                config,
+                worker_count,
+                score,
+                |result| {
+                    results.push(result);
+                    Ok(())
+                },
+            )?;
+            writer.write_all(&postcard::to_allocvec(&results)?)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let format = WireFormat::from_env();
+    let worker_count = worker_count_from_env();
+    let explain = explain_from_env();
+    let now_ts = now_ts_from_env();
+
+    let (job_sender, job_receiver) = mpsc::sync_channel(JOB_CHANNEL_CAPACITY);
 
-    // Deserialize the input on a separate thread
-    thread::spawn(move || {
-        let auction_processor = AuctionProcessor { sender };
+    // Deserialize the input on a separate thread. The handle is joined below so a
+    // decode panic surfaces as a real error instead of letting `main` exit 0 with
+    // truncated output.
+    let decoder = thread::spawn(move || {
         let reader = BufReader::new(io::stdin());
-        let mut deserializer = serde_json::Deserializer::from_reader(reader);
-        deserializer.deserialize_seq(auction_processor).unwrap();
+        read_auctions(format, reader, job_sender);
     });
 
     let config = auction_config::get_config(CONFIG_PATH);
     let writer = BufWriter::new(io::stdout());
-    let mut serializer = serde_json::Serializer::new(writer);
-    let mut seq_serializer = serializer.serialize_seq(None)?;
 
-    for auction in receiver {
-        let winning_bids = auction::get_winning_bids(&auction, &config);
-        seq_serializer.serialize_element(&winning_bids)?;
+    let result = if explain {
+        run(format, job_receiver, config, worker_count, writer, move |auction, config| {
+            // An auction that hasn't started yet or has already ended produces no winners,
+            // but still reports why every bid was rejected instead of going silent.
+            if auction::auction_status(auction, now_ts) != auction::AuctionStatus::Open {
+                return AuctionReport {
+                    winners: Vec::new(),
+                    rejections: auction::reject_closed_auction(auction)
+                        .into_iter()
+                        .map(auction::BidRejection::into_owned)
+                        .collect(),
+                };
+            }
+
+            let (winners, rejections) = auction::get_winning_bids_with_rejections(auction, config);
+            AuctionReport {
+                winners: winners.into_iter().map(auction::WinningBidResult::into_owned).collect(),
+                rejections: rejections.into_iter().map(auction::BidRejection::into_owned).collect(),
+            }
+        })
+    } else {
+        run(format, job_receiver, config, worker_count, writer, move |auction, config| {
+            // An auction that hasn't started yet or has already ended produces no winners
+            if auction::auction_status(auction, now_ts) != auction::AuctionStatus::Open {
+                return Vec::new();
+            }
+
+            auction::get_winning_bids(auction, config)
+                .into_iter()
+                .map(auction::WinningBidResult::into_owned)
+                .collect()
+        })
+    };
+
+    decoder.join().map_err(|_| "input decoder thread panicked")?;
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &'static [&'static str]) -> impl Iterator<Item = String> {
+        args.iter().copied().map(|arg| arg.to_string())
     }
 
-    seq_serializer.end()
+    fn no_env(_name: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn test_wire_format_parse_accepts_known_formats_case_insensitively() {
+        assert_eq!(WireFormat::parse("json"), Some(WireFormat::Json));
+        assert_eq!(WireFormat::parse("JSON"), Some(WireFormat::Json));
+        assert_eq!(WireFormat::parse("msgpack"), Some(WireFormat::MessagePack));
+        assert_eq!(WireFormat::parse("MessagePack"), Some(WireFormat::MessagePack));
+        assert_eq!(WireFormat::parse("Postcard"), Some(WireFormat::Postcard));
+    }
+
+    #[test]
+    fn test_wire_format_parse_rejects_unknown_format() {
+        assert_eq!(WireFormat::parse("yaml"), None);
+        assert_eq!(WireFormat::parse(""), None);
+    }
+
+    #[test]
+    fn test_wire_format_resolve_defaults_to_json() {
+        assert_eq!(WireFormat::resolve(args(&[]), no_env), WireFormat::Json);
+    }
+
+    #[test]
+    fn test_wire_format_resolve_reads_separate_flag() {
+        assert_eq!(WireFormat::resolve(args(&["--format", "postcard"]), no_env), WireFormat::Postcard);
+    }
+
+    #[test]
+    fn test_wire_format_resolve_reads_equals_flag() {
+        assert_eq!(WireFormat::resolve(args(&["--format=msgpack"]), no_env), WireFormat::MessagePack);
+    }
+
+    #[test]
+    fn test_wire_format_resolve_falls_back_to_env_var() {
+        assert_eq!(
+            WireFormat::resolve(args(&[]), |name| if name == "AUCTION_FORMAT" {
+                Some("postcard".to_string())
+            } else {
+                None
+            }),
+            WireFormat::Postcard
+        );
+    }
+
+    #[test]
+    fn test_wire_format_resolve_cli_flag_beats_env_var() {
+        assert_eq!(
+            WireFormat::resolve(args(&["--format=json"]), |name| if name == "AUCTION_FORMAT" {
+                Some("postcard".to_string())
+            } else {
+                None
+            }),
+            WireFormat::Json
+        );
+    }
+
+    #[test]
+    fn test_resolve_worker_count_falls_back_to_default_when_unset_or_invalid() {
+        assert_eq!(resolve_worker_count(no_env, || 4), 4);
+        assert_eq!(resolve_worker_count(|_| Some("0".to_string()), || 4), 4);
+        assert_eq!(resolve_worker_count(|_| Some("not-a-number".to_string()), || 4), 4);
+    }
+
+    #[test]
+    fn test_resolve_worker_count_reads_env_var() {
+        assert_eq!(resolve_worker_count(|_| Some("8".to_string()), || 4), 8);
+    }
+
+    #[test]
+    fn test_resolve_explain_reads_flag_or_env_var() {
+        assert!(!resolve_explain(args(&[]), no_env));
+        assert!(resolve_explain(args(&["--explain"]), no_env));
+        assert!(resolve_explain(args(&[]), |name| if name == "AUCTION_EXPLAIN" {
+            Some("1".to_string())
+        } else {
+            None
+        }));
+    }
+
+    #[test]
+    fn test_resolve_now_ts_reads_flag_then_env_then_default() {
+        assert_eq!(resolve_now_ts(args(&["--now", "100"]), no_env, || 999), 100);
+        assert_eq!(resolve_now_ts(args(&["--now=200"]), no_env, || 999), 200);
+        assert_eq!(
+            resolve_now_ts(args(&[]), |name| if name == "AUCTION_NOW" { Some("300".to_string()) } else { None }, || 999),
+            300
+        );
+        assert_eq!(resolve_now_ts(args(&[]), no_env, || 999), 999);
+    }
 }